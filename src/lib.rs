@@ -15,10 +15,13 @@
 
 use core::time::Duration;
 use nix::poll::{poll, PollFd, PollFlags};
+use nix::sys::signal::{self, Signal};
+use nix::unistd::Pid;
 use nonblock::NonBlockingReader;
 use std::io::{ErrorKind, LineWriter, Read, Write};
 use std::os::fd::{AsRawFd, RawFd};
-use std::path::Path;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
 use std::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command, Stdio};
 
 /// A ghci session handle
@@ -39,6 +42,13 @@ pub struct Ghci {
     stderr_fd: RawFd,
     /// Current timeout value
     timeout: Option<Duration>,
+    /// Session-unique marker used to detect the `ghci` prompt boundary in the output, including
+    /// its trailing newline
+    prompt: String,
+    /// Whether `:set +s` is enabled, so [`eval`](Ghci::eval) should parse a trailing timing line
+    show_timing: bool,
+    /// Whether `:set +t` is enabled, so [`eval`](Ghci::eval) should parse a trailing type line
+    show_type: bool,
 }
 
 #[derive(Debug)]
@@ -49,6 +59,75 @@ pub struct EvalOutput {
     pub stdout: String,
     /// stderr for the result of the ghci evaluation
     pub stderr: String,
+    /// Timing/allocation stats for the evaluation, present when enabled with
+    /// [`Ghci::set_show_timing`]
+    pub timing: Option<Timing>,
+    /// The type of the evaluated expression, present when enabled with [`Ghci::set_show_type`]
+    pub result_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Timing/allocation statistics captured by [`Ghci::set_show_timing`]
+pub struct Timing {
+    /// Wall-clock time the evaluation took
+    pub elapsed: Duration,
+    /// Number of bytes allocated during the evaluation
+    pub bytes_allocated: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Which stream a line of output from [`Ghci::eval_stream`] came from
+pub enum Stream {
+    /// Standard output
+    Stdout,
+    /// Standard error
+    Stderr,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+/// Structured result of a [`Ghci::load`]/[`Ghci::reload`]
+pub struct LoadResult {
+    /// Warnings and errors reported while loading
+    pub diagnostics: Vec<Diagnostic>,
+    /// Modules currently loaded in the session, as reported by `:show modules`, one per line
+    /// (e.g. `Foo ( Foo.hs, interpreted )`)
+    ///
+    /// This is sourced independently of the diagnostics above because `ghci` is spawned with
+    /// `-v0`, which suppresses the `[1 of N] Compiling ...`/`Ok, modules loaded: ...` narration
+    /// that would otherwise need to be scraped from `:load`'s own output.
+    pub loaded_modules: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A single compiler diagnostic reported by GHC while loading modules
+pub struct Diagnostic {
+    /// Whether this diagnostic is a warning or an error
+    pub severity: Severity,
+    /// Source location the diagnostic points at, if GHC reported one
+    pub location: Option<Location>,
+    /// The diagnostic message body, with the leading location/severity header stripped
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Severity of a [`Diagnostic`]
+pub enum Severity {
+    /// A warning, reported with `-W...` flags but which doesn't prevent loading
+    Warning,
+    /// An error, which prevents the module from loading
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Source location of a [`Diagnostic`]
+pub struct Location {
+    /// Path to the source file, as reported by GHC
+    pub file: PathBuf,
+    /// 1-based line number
+    pub line: u32,
+    /// 1-based column number
+    pub column: u32,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -57,7 +136,8 @@ pub struct EvalOutput {
 pub enum GhciError {
     /// The evaluation timed out
     ///
-    /// Note: The Ghci session is not in a good state and needs to be killed
+    /// Note: The underlying evaluation is still running; call [`Ghci::interrupt`] to recover the
+    /// session
     #[error("ghci session timed out waiting on output")]
     Timeout,
     /// IO error from the underlying child process management
@@ -71,11 +151,64 @@ pub enum GhciError {
 /// A convenient alias for [`std::result::Result`] using a [`GhciError`]
 pub type Result<T> = std::result::Result<T, GhciError>;
 
-// Use a prompt that is unlikely to be part of the stdout of the ghci session
-const PROMPT: &str = "__ghci_rust_prompt__>\n";
+/// A builder to configure a [`Ghci`] session before it is spawned
+///
+/// `ghci` is spawned with exactly `-v0 --interactive -ignore-dot-ghci` plus whatever extra flags
+/// are added with [`GhciBuilder::flag`], so that sessions are deterministic and don't
+/// accidentally pick up a user's `.ghci`.
+///
+/// ```
+/// # use ghci::GhciBuilder;
+/// let mut ghci = GhciBuilder::new()
+///     .flag("-XOverloadedStrings")
+///     .spawn()
+///     .unwrap();
+/// ```
+#[derive(Debug, Default)]
+pub struct GhciBuilder {
+    flags: Vec<String>,
+    working_dir: Option<PathBuf>,
+    env: Vec<(String, String)>,
+    load: Vec<PathBuf>,
+}
 
-impl Ghci {
-    /// Create a new ghci session
+impl GhciBuilder {
+    /// Create a new builder with no extra flags, the current working directory and
+    /// environment, and no files to load
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an extra flag to pass to `ghci` on startup (e.g. `-package`, `-XOverloadedStrings`)
+    #[must_use]
+    pub fn flag(mut self, flag: impl Into<String>) -> Self {
+        self.flags.push(flag.into());
+        self
+    }
+
+    /// Set the working directory the `ghci` process is spawned in
+    #[must_use]
+    pub fn working_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.working_dir = Some(dir.into());
+        self
+    }
+
+    /// Set an environment variable for the `ghci` process
+    #[must_use]
+    pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+
+    /// Add a file to [`Ghci::load`] once the session has started
+    #[must_use]
+    pub fn load(mut self, path: impl Into<PathBuf>) -> Self {
+        self.load.push(path.into());
+        self
+    }
+
+    /// Spawn the `ghci` session with the configured options
     ///
     /// It will use `ghci` on your `PATH` by default, but can be overridden to use any `ghci` by
     /// setting the `GHCI_PATH` environment variable pointing at the binary to use
@@ -83,18 +216,33 @@ impl Ghci {
     /// # Errors
     ///
     /// Returns [`IOError`] when it encounters IO errors as part of spawning the `ghci` subprocess
+    /// or [`Ghci::load`]ing the configured files
     ///
     /// [`IOError`]: GhciError::IOError
-    pub fn new() -> Result<Self> {
+    pub fn spawn(self) -> Result<Ghci> {
         const PIPE_ERR: &str = "pipe should be present";
 
         let ghci = std::env::var("GHCI_PATH").unwrap_or_else(|_| "ghci".to_string());
 
-        let mut child = Command::new(ghci)
+        let mut command = Command::new(ghci);
+        command
+            .arg("-v0")
+            .arg("--interactive")
+            .arg("-ignore-dot-ghci")
+            .args(&self.flags)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
-            .spawn()?;
+            // Spawn ghci in its own process group so that `interrupt` can signal it without
+            // also signalling this process
+            .process_group(0)
+            .envs(self.env);
+
+        if let Some(working_dir) = &self.working_dir {
+            command.current_dir(working_dir);
+        }
+
+        let mut child = command.spawn()?;
 
         let mut stdin = LineWriter::new(child.stdin.take().expect(PIPE_ERR));
         let mut stdout = child.stdout.take().expect(PIPE_ERR);
@@ -102,16 +250,20 @@ impl Ghci {
 
         clear_blocking_reader_until(&mut stdout, b"> ")?;
 
+        // Use a random, high-entropy prompt so that it can't collide with the stdout of the
+        // ghci session, however unlikely that would be with a fixed prompt
+        let prompt = format!("{}>\n", random_marker());
+
         // Setup a known prompt/multi-line prompt
         stdin.write_all(b":set prompt \"")?;
-        stdin.write_all(PROMPT[..PROMPT.len() - 1].as_bytes())?;
+        stdin.write_all(prompt[..prompt.len() - 1].as_bytes())?;
         stdin.write_all(b"\\n\"\n")?;
-        clear_blocking_reader_until(&mut stdout, PROMPT.as_bytes())?;
+        clear_blocking_reader_until(&mut stdout, prompt.as_bytes())?;
 
         stdin.write_all(b":set prompt-cont \"\"\n")?;
-        clear_blocking_reader_until(&mut stdout, PROMPT.as_bytes())?;
+        clear_blocking_reader_until(&mut stdout, prompt.as_bytes())?;
 
-        Ok(Self {
+        let mut ghci = Ghci {
             stdin,
             stdout_fd: stdout.as_raw_fd(),
             stdout: NonBlockingReader::from_fd(stdout)?,
@@ -119,7 +271,36 @@ impl Ghci {
             stderr: NonBlockingReader::from_fd(stderr)?,
             child,
             timeout: None,
-        })
+            prompt,
+            show_timing: false,
+            show_type: false,
+        };
+
+        if !self.load.is_empty() {
+            let paths: Vec<&Path> = self.load.iter().map(PathBuf::as_path).collect();
+            ghci.load(&paths)?;
+        }
+
+        Ok(ghci)
+    }
+}
+
+impl Ghci {
+    /// Create a new ghci session with the default options
+    ///
+    /// It will use `ghci` on your `PATH` by default, but can be overridden to use any `ghci` by
+    /// setting the `GHCI_PATH` environment variable pointing at the binary to use
+    ///
+    /// This is a shortcut for spawning a default [`GhciBuilder`]. Use the builder directly to
+    /// configure startup flags, the working directory, environment variables, or files to load.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IOError`] when it encounters IO errors as part of spawning the `ghci` subprocess
+    ///
+    /// [`IOError`]: GhciError::IOError
+    pub fn new() -> Result<Self> {
+        GhciBuilder::new().spawn()
     }
 
     /// Evaluate/run a statement
@@ -193,14 +374,183 @@ impl Ghci {
             if poll_fds[1].any() == Some(true) {
                 self.stdout.read_available_to_string(&mut stdout)?;
 
-                if stdout.ends_with(PROMPT) {
-                    stdout.truncate(stdout.len() - PROMPT.len());
+                if stdout.ends_with(&self.prompt) {
+                    stdout.truncate(stdout.len() - self.prompt.len());
+                    break;
+                }
+            }
+        }
+
+        // `:set +t` prints `it :: T` then `:set +s` prints `(x.xx secs, N bytes)`, in that order,
+        // after the statement's own output, so they are stripped from the trailing end of
+        // stdout rather than left for the caller to scrape. The line is only removed once it is
+        // confirmed to match, so an eval that doesn't end in such a line (e.g. `:load`) keeps its
+        // real last line intact.
+        let timing = self
+            .show_timing
+            .then(|| pop_last_line_if(&mut stdout, parse_timing))
+            .flatten();
+
+        let result_type = self
+            .show_type
+            .then(|| pop_last_line_if(&mut stdout, parse_result_type))
+            .flatten();
+
+        Ok(EvalOutput {
+            stdout,
+            stderr,
+            timing,
+            result_type,
+        })
+    }
+
+    /// Evaluate/run a statement, streaming output line by line as it is produced
+    ///
+    /// Unlike [`Ghci::eval`], this does not buffer the whole output until the evaluation
+    /// completes: `on_output` is called with each line of output, tagged with the [`Stream`] it
+    /// came from, as soon as it is available. This is useful for long-running or chatty
+    /// evaluations where progress needs to be reported incrementally.
+    ///
+    /// ```
+    /// # use ghci::{Ghci, Stream};
+    /// let mut ghci = Ghci::new().unwrap();
+    ///
+    /// let mut lines = Vec::new();
+    /// ghci.eval_stream("putStrLn \"Hello world\"", |stream, line| {
+    ///     lines.push((stream, line.to_string()));
+    /// }).unwrap();
+    ///
+    /// assert_eq!(lines, vec![(Stream::Stdout, "Hello world".to_string())]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Ghci::eval`]
+    pub fn eval_stream<F: FnMut(Stream, &str)>(
+        &mut self,
+        input: &str,
+        mut on_output: F,
+    ) -> Result<()> {
+        self.stdin.write_all(b":{\n")?;
+        self.stdin.write_all(input.as_bytes())?;
+        self.stdin.write_all(b"\n:}\n")?;
+
+        let mut stdout = String::new();
+        let mut stderr = String::new();
+        let timeout = self
+            .timeout
+            .and_then(|d| d.as_millis().try_into().ok())
+            .unwrap_or(-1);
+
+        loop {
+            let mut poll_fds = [
+                PollFd::new(self.stderr_fd, PollFlags::POLLIN),
+                PollFd::new(self.stdout_fd, PollFlags::POLLIN),
+            ];
+
+            let ret = poll(&mut poll_fds, timeout)?;
+
+            if ret == 0 {
+                return Err(GhciError::Timeout);
+            }
+
+            if poll_fds[0].any() == Some(true) {
+                self.stderr.read_available_to_string(&mut stderr)?;
+                emit_lines(&mut stderr, |line| on_output(Stream::Stderr, line));
+            }
+
+            if poll_fds[1].any() == Some(true) {
+                self.stdout.read_available_to_string(&mut stdout)?;
+
+                let done = stdout.ends_with(&self.prompt);
+                if done {
+                    stdout.truncate(stdout.len() - self.prompt.len());
+                }
+
+                emit_lines(&mut stdout, |line| on_output(Stream::Stdout, line));
+
+                if done {
+                    break;
+                }
+            }
+        }
+
+        // `emit_lines` only flushes newline-terminated lines, so anything left over is a final
+        // partial line (e.g. `putStr` output) that was never followed by a newline before the
+        // prompt appeared
+        if !stderr.is_empty() {
+            on_output(Stream::Stderr, &stderr);
+        }
+
+        if !stdout.is_empty() {
+            on_output(Stream::Stdout, &stdout);
+        }
+
+        Ok(())
+    }
+
+    /// Interrupt the evaluation currently running in the session
+    ///
+    /// Sends `SIGINT` to the `ghci` process, which aborts the statement being evaluated, then
+    /// waits for the prompt to reappear so the session can be reused. This is the way to recover
+    /// a session after a [`GhciError::Timeout`], instead of having to [`close`] it.
+    ///
+    /// If the evaluation already completed on its own before the signal arrives, `ghci` will not
+    /// print an `Interrupted.` line but the prompt will still be there, so this just drains it.
+    /// The drain is subject to the same timeout (set by [`Ghci::set_timeout`]) as [`Ghci::eval`],
+    /// so calling this when the session is already idle (no prompt to wait for) surfaces as a
+    /// [`Timeout`] rather than hanging forever - *provided* a timeout has actually been set. By
+    /// default no timeout is configured, in which case this (like [`Ghci::eval`]) blocks
+    /// indefinitely until the prompt reappears.
+    ///
+    /// # Errors
+    ///
+    /// - Returns a [`Timeout`] if the timeout is reached before the prompt reappears.
+    /// - Returns a [`PollError`] if sending the signal, or waiting for the prompt, fails.
+    /// - Returns a [`IOError`] when it encounters an IO error on the `ghci` subprocess `stdout`
+    ///   or `stderr`.
+    ///
+    /// [`close`]: Ghci::close
+    /// [`Timeout`]: GhciError::Timeout
+    /// [`PollError`]: GhciError::PollError
+    /// [`IOError`]: GhciError::IOError
+    pub fn interrupt(&mut self) -> Result<()> {
+        let pid = Pid::from_raw(i32::try_from(self.child.id()).unwrap_or(i32::MAX));
+        signal::kill(pid, Signal::SIGINT)?;
+
+        let mut stdout = String::new();
+        let mut stderr = String::new();
+        let timeout = self
+            .timeout
+            .and_then(|d| d.as_millis().try_into().ok())
+            .unwrap_or(-1);
+
+        loop {
+            let mut poll_fds = [
+                PollFd::new(self.stderr_fd, PollFlags::POLLIN),
+                PollFd::new(self.stdout_fd, PollFlags::POLLIN),
+            ];
+
+            let ret = poll(&mut poll_fds, timeout)?;
+
+            if ret == 0 {
+                return Err(GhciError::Timeout);
+            }
+
+            if poll_fds[0].any() == Some(true) {
+                self.stderr.read_available_to_string(&mut stderr)?;
+            }
+
+            if poll_fds[1].any() == Some(true) {
+                self.stdout.read_available_to_string(&mut stdout)?;
+
+                if stdout.ends_with(&self.prompt) {
                     break;
                 }
             }
         }
 
-        Ok(EvalOutput { stdout, stderr })
+        Ok(())
     }
 
     /// Set a timeout for evaluations
@@ -222,10 +572,9 @@ impl Ghci {
     ///
     /// By default, no timeout is set.
     ///
-    /// Note: When a [`Timeout`] error is triggered, the `ghci` session **must** be closed with
-    /// [`Ghci::close`] or [`Drop`]ed in order to properly stop the corresponding evaluation.
-    /// If the evaluation is left to finish after a timeout occurs, the session is then left in a
-    /// bad state that is not recoverable.
+    /// Note: When a [`Timeout`] error is triggered, the underlying evaluation is still running.
+    /// Call [`Ghci::interrupt`] to abort it and recover the session, or close the session with
+    /// [`Ghci::close`] or [`Drop`] if it is no longer needed.
     ///
     /// [`Timeout`]: GhciError::Timeout
     #[inline]
@@ -233,6 +582,63 @@ impl Ghci {
         self.timeout = timeout;
     }
 
+    /// Enable or disable reporting timing/allocation stats (`:set +s`/`:unset +s`)
+    ///
+    /// When enabled, [`Ghci::eval`] populates [`EvalOutput::timing`] instead of leaving the
+    /// `(x.xx secs, N bytes)` line in `stdout`.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Ghci::eval`]
+    pub fn set_show_timing(&mut self, enabled: bool) -> Result<()> {
+        self.eval(if enabled { ":set +s" } else { ":unset +s" })?;
+        self.show_timing = enabled;
+        Ok(())
+    }
+
+    /// Enable or disable reporting the type of evaluated expressions (`:set +t`/`:unset +t`)
+    ///
+    /// When enabled, [`Ghci::eval`] populates [`EvalOutput::result_type`] instead of leaving the
+    /// `it :: T` line in `stdout`.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Ghci::eval`]
+    pub fn set_show_type(&mut self, enabled: bool) -> Result<()> {
+        self.eval(if enabled { ":set +t" } else { ":unset +t" })?;
+        self.show_type = enabled;
+        Ok(())
+    }
+
+    /// Get the type of an expression, using `:type`
+    ///
+    /// ```
+    /// # use ghci::Ghci;
+    /// let mut ghci = Ghci::new().unwrap();
+    /// assert_eq!(&ghci.type_of("True").unwrap(), "Bool");
+    /// ```
+    ///
+    /// This works regardless of whether [`Ghci::set_show_type`] is enabled: `eval` would
+    /// otherwise strip `:type`'s only line of output as the trailing `it :: T` line meant for
+    /// [`EvalOutput::result_type`], leaving nothing behind for `type_of` to read.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Ghci::eval`]
+    pub fn type_of(&mut self, expr: &str) -> Result<String> {
+        let line = format!(":type {expr}");
+
+        let show_type = self.show_type;
+        self.show_type = false;
+        let output = self.eval(&line);
+        self.show_type = show_type;
+
+        let output = output?;
+        let line = output.stdout.trim_end();
+
+        Ok(parse_result_type(line).unwrap_or_else(|| line.to_string()))
+    }
+
     /// Import multiple modules
     ///
     /// ```
@@ -256,20 +662,61 @@ impl Ghci {
 
     /// Load multiple modules by file path
     ///
+    /// The compiler diagnostics (warnings and errors) are parsed out of the `ghci` output, and
+    /// the set of currently loaded modules is queried separately with `:show modules`; both are
+    /// returned together as a [`LoadResult`] rather than left as raw text.
+    ///
     /// # Errors
     ///
     /// Same as [`Ghci::eval`]
     #[inline]
-    pub fn load(&mut self, paths: &[&Path]) -> Result<()> {
+    pub fn load(&mut self, paths: &[&Path]) -> Result<LoadResult> {
         let mut line = String::from(":load");
 
         for path in paths {
             line.push_str(&format!(" {}", path.display()));
         }
 
-        self.eval(&line)?;
+        let output = self.eval(&line)?;
+        let diagnostics = parse_diagnostics(&output.stderr);
+        let loaded_modules = self.show_modules()?;
 
-        Ok(())
+        Ok(LoadResult {
+            diagnostics,
+            loaded_modules,
+        })
+    }
+
+    /// Reload the previously [`Ghci::load`]ed modules, picking up changes made on disk
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Ghci::eval`]
+    #[inline]
+    pub fn reload(&mut self) -> Result<LoadResult> {
+        let output = self.eval(":reload")?;
+        let diagnostics = parse_diagnostics(&output.stderr);
+        let loaded_modules = self.show_modules()?;
+
+        Ok(LoadResult {
+            diagnostics,
+            loaded_modules,
+        })
+    }
+
+    /// Query the modules currently loaded in the session, via `:show modules`
+    ///
+    /// Unlike the `[1 of N] Compiling ...`/`Ok, modules loaded: ...` lines `ghci` would otherwise
+    /// print while loading, `:show modules` is not suppressed by `-v0`, so this is the reliable
+    /// source for [`LoadResult::loaded_modules`].
+    fn show_modules(&mut self) -> Result<Vec<String>> {
+        let output = self.eval(":show modules")?;
+        Ok(output
+            .stdout
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect())
     }
 
     /// Close the ghci session
@@ -298,6 +745,160 @@ impl Drop for Ghci {
     }
 }
 
+// Generate a high-entropy, session-unique marker (256 bits, rendered as hex) used to build the
+// ghci prompt, so that it can't collide with the stdout of the ghci session.
+//
+// This crate has no manifest checked into this tree to pin versions in, so note them here instead
+// until one exists downstream: `rand = "0.8"`, alongside the existing `nix = "0.26"` (pinned for
+// its pre-0.27 `PollFd::new(RawFd, PollFlags)` signature), `nonblock = "0.2"`, and `thiserror =
+// "1"`.
+fn random_marker() -> String {
+    use std::fmt::Write as _;
+
+    let bytes: [u8; 32] = rand::random();
+    bytes.iter().fold(String::new(), |mut marker, byte| {
+        let _ = write!(marker, "{byte:02x}");
+        marker
+    })
+}
+
+// Find the start index of the last line (without its trailing newline) in a buffer that ends
+// with a newline, e.g. the stdout produced by `Ghci::eval`
+fn last_line_start(buffer: &str) -> Option<usize> {
+    let without_trailing_newline = buffer.strip_suffix('\n')?;
+    Some(
+        without_trailing_newline
+            .rfind('\n')
+            .map_or(0, |index| index + 1),
+    )
+}
+
+// Remove and return the last line of `buffer`, but only if `parse` recognizes it; otherwise
+// `buffer` is left untouched so the line is kept as regular output
+fn pop_last_line_if<T>(buffer: &mut String, parse: impl FnOnce(&str) -> Option<T>) -> Option<T> {
+    let start = last_line_start(buffer)?;
+    let value = parse(&buffer[start..buffer.len() - 1])?;
+    buffer.truncate(start);
+
+    Some(value)
+}
+
+// Parse the `(x.xx secs, N bytes)` line printed by `:set +s`
+fn parse_timing(line: &str) -> Option<Timing> {
+    let inner = line.strip_prefix('(')?.strip_suffix(')')?;
+    let (secs, bytes_allocated) = inner.split_once(" secs, ")?;
+    let bytes_allocated = bytes_allocated.strip_suffix(" bytes")?;
+
+    Some(Timing {
+        elapsed: Duration::from_secs_f64(secs.parse().ok()?),
+        bytes_allocated: bytes_allocated.replace(',', "").parse().ok()?,
+    })
+}
+
+// Parse the `it :: T` line printed by `:set +t` (and the output of `:type`), returning just `T`.
+// Returns `None` when the line doesn't look like a type line, e.g. it isn't the trailing line
+// that `:set +t` prints.
+fn parse_result_type(line: &str) -> Option<String> {
+    line.split_once(" :: ")
+        .map(|(_, result_type)| result_type.to_string())
+}
+
+// Pull complete (newline-terminated) lines out of `buffer`, invoking `callback` for each one
+// and leaving any trailing partial line in place for the next call
+fn emit_lines(buffer: &mut String, mut callback: impl FnMut(&str)) {
+    while let Some(index) = buffer.find('\n') {
+        let line: String = buffer.drain(..=index).collect();
+        callback(line.trim_end_matches('\n'));
+    }
+}
+
+// Parse the diagnostics (warnings and errors) out of the output of `:load`/`:reload`.
+//
+// A diagnostic starts with a header line of the form `path:line:col: warning:` or
+// `path:line:col: error:` (GHC also emits a range form, `path:(l1,c1)-(l2,c2): error:`), followed
+// by indented lines that belong to it until the next header or a blank line. Everything else
+// (e.g. the module-loading summary, which `-v0` suppresses in practice) is noise and is dropped;
+// see [`Ghci::show_modules`] for how loaded modules are actually discovered.
+fn parse_diagnostics(stdout: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    let mut lines = stdout.lines().peekable();
+    while let Some(line) = lines.next() {
+        if let Some((location, severity, header_rest)) = parse_diagnostic_header(line) {
+            let mut message = header_rest.trim_start().to_string();
+
+            while let Some(next) = lines.peek() {
+                if next.is_empty() || parse_diagnostic_header(next).is_some() {
+                    break;
+                }
+
+                if !message.is_empty() {
+                    message.push('\n');
+                }
+                message.push_str(next);
+                lines.next();
+            }
+
+            diagnostics.push(Diagnostic {
+                severity,
+                location,
+                message,
+            });
+        }
+    }
+
+    diagnostics
+}
+
+// Recognize a diagnostic header line, returning its parsed location (if any), severity, and the
+// remainder of the line after the header.
+//
+// Header lines are never indented, unlike the message lines that follow them, so a line that
+// merely contains `": warning:"`/`": error:"` somewhere in its (indented) body - e.g. a message
+// quoting another source location - is not mistaken for the start of a new diagnostic.
+fn parse_diagnostic_header(line: &str) -> Option<(Option<Location>, Severity, &str)> {
+    if line.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let (location_str, severity, message) = if let Some((loc, msg)) = line.split_once(": warning:")
+    {
+        (loc, Severity::Warning, msg)
+    } else if let Some((loc, msg)) = line.split_once(": error:") {
+        (loc, Severity::Error, msg)
+    } else {
+        return None;
+    };
+
+    Some((parse_location(location_str), severity, message))
+}
+
+// Parse a GHC source location, either `path:line:col` or the range form `path:(l1,c1)-(l2,c2)`
+// (in which case the start of the range is used)
+fn parse_location(location: &str) -> Option<Location> {
+    if let Some((file, range)) = location.split_once(":(") {
+        let (start, _end) = range.split_once(")-(")?;
+        let (line, column) = start.split_once(',')?;
+
+        return Some(Location {
+            file: PathBuf::from(file),
+            line: line.parse().ok()?,
+            column: column.parse().ok()?,
+        });
+    }
+
+    let mut parts = location.rsplitn(3, ':');
+    let column = parts.next()?;
+    let line = parts.next()?;
+    let file = parts.next()?;
+
+    Some(Location {
+        file: PathBuf::from(file),
+        line: line.parse().ok()?,
+        column: column.parse().ok()?,
+    })
+}
+
 // Helper function to clear data from a blocking reader until a pattern is seen
 // - the pattern is also cleared
 // - the pattern has to be at the end of a given read (otherwise it will hang)
@@ -330,4 +931,120 @@ mod tests {
         let res = ghci.eval("x ::").unwrap();
         assert!(res.stderr.contains("parse error"));
     }
+
+    #[test]
+    fn type_of_unaffected_by_show_type() {
+        // `eval` strips the trailing `it :: T` line whenever `show_type` is enabled, so
+        // `type_of`'s own `:type` call must not be subject to that stripping.
+        let mut ghci = Ghci::new().unwrap();
+        ghci.set_show_type(true).unwrap();
+
+        assert_eq!(&ghci.type_of("True").unwrap(), "Bool");
+    }
+
+    #[test]
+    fn load_populates_modules() {
+        // `ghci` is spawned with `-v0`, so `loaded_modules` can't just scrape `:load`'s own
+        // (suppressed) module-loading summary - this exercises the real `:show modules` path
+        // against the builder's actual default flags.
+        let path = std::env::temp_dir().join(format!("ghci_rs_test_{}.hs", std::process::id()));
+        std::fs::write(&path, "module Foo where\nfoo :: Int\nfoo = 42\n").unwrap();
+
+        let mut ghci = Ghci::new().unwrap();
+        let result = ghci.load(&[path.as_path()]).unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(result.diagnostics.is_empty());
+        assert_eq!(result.loaded_modules.len(), 1);
+        assert!(result.loaded_modules[0].starts_with("Foo "));
+    }
+
+    #[test]
+    fn parse_load_diagnostics() {
+        let stdout = concat!(
+            "Foo.hs:3:1: warning: [GHC-missing-signatures]\n",
+            "    Top-level binding with no type signature: foo :: Int\n",
+            "\n",
+            "Foo.hs:(5,1)-(5,10): error:\n",
+            "    Variable not in scope: bar\n",
+        );
+
+        let diagnostics = parse_diagnostics(stdout);
+
+        assert_eq!(diagnostics.len(), 2);
+
+        let warning = &diagnostics[0];
+        assert_eq!(warning.severity, Severity::Warning);
+        assert_eq!(
+            warning.location,
+            Some(Location {
+                file: PathBuf::from("Foo.hs"),
+                line: 3,
+                column: 1,
+            })
+        );
+
+        let error = &diagnostics[1];
+        assert_eq!(error.severity, Severity::Error);
+        assert_eq!(
+            error.location,
+            Some(Location {
+                file: PathBuf::from("Foo.hs"),
+                line: 5,
+                column: 1,
+            })
+        );
+        assert_eq!(error.message, "    Variable not in scope: bar");
+    }
+
+    #[test]
+    fn parse_diagnostic_header_ignores_indented_lookalike() {
+        // A message body line that happens to contain ": warning:"/": error:" (e.g. quoting
+        // another source location) must not be mistaken for the start of a new diagnostic, since
+        // header lines are never indented.
+        let stdout = concat!(
+            "Foo.hs:3:1: warning: [GHC-deprecations]\n",
+            "    In the use of `bar' (imported from Bar):\n",
+            "    \"deprecated: use `baz' instead, see Bar.hs:1:1: warning: notice\"\n",
+        );
+
+        let diagnostics = parse_diagnostics(stdout);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "[GHC-deprecations]\n    In the use of `bar' (imported from Bar):\n    \"deprecated: use `baz' instead, see Bar.hs:1:1: warning: notice\""
+        );
+    }
+
+    #[test]
+    fn parse_timing_and_type() {
+        let mut stdout = String::from("42\nit :: Int\n(0.00 secs, 123,456 bytes)\n");
+
+        let timing = pop_last_line_if(&mut stdout, parse_timing);
+        assert_eq!(
+            timing,
+            Some(Timing {
+                elapsed: Duration::from_secs_f64(0.0),
+                bytes_allocated: 123_456,
+            })
+        );
+
+        let result_type = pop_last_line_if(&mut stdout, parse_result_type);
+        assert_eq!(result_type, Some("Int".to_string()));
+
+        assert_eq!(stdout, "42\n");
+    }
+
+    #[test]
+    fn pop_last_line_if_keeps_non_matching_line() {
+        // A real last line (e.g. `:load`'s module-loading summary) must survive untouched when
+        // it doesn't actually look like a timing/type line
+        let mut stdout = String::from("Ok, modules loaded: Foo.\n");
+
+        assert_eq!(pop_last_line_if(&mut stdout, parse_timing), None);
+        assert_eq!(pop_last_line_if(&mut stdout, parse_result_type), None);
+        assert_eq!(stdout, "Ok, modules loaded: Foo.\n");
+    }
 }